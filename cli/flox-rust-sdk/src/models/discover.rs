@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    #[error("failed to read directory '{path}': {source}")]
+    ReadingDir {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Recursively find `.flox` directories under `root`, honoring `.gitignore` files
+///
+/// As the walk descends into a directory, that directory's `.gitignore` (if
+/// any) is pushed onto a stack so its patterns apply to it and everything
+/// below it, and popped again once the walk backs out. `.git` directories
+/// and `.flox` directories themselves are never descended into.
+pub fn discover_dot_flox_dirs(root: impl AsRef<Path>) -> Result<Vec<PathBuf>, DiscoveryError> {
+    let mut found = Vec::new();
+    let mut ignores = Vec::new();
+    walk(root.as_ref(), &mut ignores, &mut found)?;
+    Ok(found)
+}
+
+fn walk(
+    dir: &Path,
+    ignores: &mut Vec<Gitignore>,
+    found: &mut Vec<PathBuf>,
+) -> Result<(), DiscoveryError> {
+    let pushed = push_gitignore(dir, ignores);
+
+    let entries = std::fs::read_dir(dir).map_err(|source| DiscoveryError::ReadingDir {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+
+    for entry in entries {
+        let Ok(entry) = entry else { continue };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if !file_type.is_dir() {
+            continue;
+        }
+
+        let path = entry.path();
+        let name = entry.file_name();
+
+        if name == ".git" {
+            continue;
+        }
+
+        if is_ignored(&path, ignores) {
+            debug!(path = ?path, "skipping directory excluded by .gitignore");
+            continue;
+        }
+
+        if name == ".flox" {
+            found.push(path);
+            continue;
+        }
+
+        walk(&path, ignores, found)?;
+    }
+
+    if pushed {
+        ignores.pop();
+    }
+
+    Ok(())
+}
+
+/// Parse `dir`'s `.gitignore`, if any, and push it onto `ignores`
+///
+/// Returns whether a matcher was pushed, so the caller knows whether to pop
+/// it again once it's done descending into `dir`.
+fn push_gitignore(dir: &Path, ignores: &mut Vec<Gitignore>) -> bool {
+    let gitignore_path = dir.join(".gitignore");
+    if !gitignore_path.is_file() {
+        return false;
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&gitignore_path) {
+        debug!(path = ?gitignore_path, error = %e, "failed to parse .gitignore");
+    }
+
+    match builder.build() {
+        Ok(gitignore) => {
+            ignores.push(gitignore);
+            true
+        },
+        Err(e) => {
+            debug!(path = ?gitignore_path, error = %e, "failed to build .gitignore matcher");
+            false
+        },
+    }
+}
+
+/// Whether `path` is excluded by any of the `.gitignore` matchers currently on the stack
+fn is_ignored(path: &Path, ignores: &[Gitignore]) -> bool {
+    ignores
+        .iter()
+        .any(|gitignore| gitignore.matched(path, true).is_ignore())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Test that `.flox` directories are found, and that entries excluded by
+    /// a `.gitignore` are skipped.
+    #[test]
+    fn test_discover_honors_gitignore() {
+        let tempdir = TempDir::new().unwrap();
+        let root = tempdir.path();
+
+        fs::create_dir_all(root.join("keep/.flox")).unwrap();
+        fs::create_dir_all(root.join("skip/.flox")).unwrap();
+        fs::create_dir_all(root.join("nested/keep/.flox")).unwrap();
+
+        write(&root.join(".gitignore"), "skip/\n");
+
+        let mut found = discover_dot_flox_dirs(root).unwrap();
+        found.sort();
+
+        let mut expected = vec![
+            root.join("keep/.flox"),
+            root.join("nested/keep/.flox"),
+        ];
+        expected.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    /// Test that nested `.gitignore` files only apply to their own subtree
+    #[test]
+    fn test_nested_gitignore_scoped_to_subtree() {
+        let tempdir = TempDir::new().unwrap();
+        let root = tempdir.path();
+
+        fs::create_dir_all(root.join("a/.flox")).unwrap();
+        fs::create_dir_all(root.join("a/b/.flox")).unwrap();
+        fs::create_dir_all(root.join("c/.flox")).unwrap();
+        fs::create_dir_all(root.join("c/b/.flox")).unwrap();
+
+        write(&root.join("a/.gitignore"), "b/\n");
+
+        let mut found = discover_dot_flox_dirs(root).unwrap();
+        found.sort();
+
+        let mut expected = vec![
+            root.join("a/.flox"),
+            root.join("c/.flox"),
+            root.join("c/b/.flox"),
+        ];
+        expected.sort();
+
+        assert_eq!(found, expected);
+    }
+
+    /// Test that `.git` directories are never descended into
+    #[test]
+    fn test_skips_git_dir() {
+        let tempdir = TempDir::new().unwrap();
+        let root = tempdir.path();
+
+        fs::create_dir_all(root.join(".git/.flox")).unwrap();
+        fs::create_dir_all(root.join("project/.flox")).unwrap();
+
+        let found = discover_dot_flox_dirs(root).unwrap();
+
+        assert_eq!(found, vec![root.join("project/.flox")]);
+    }
+}
@@ -1,14 +1,61 @@
 use std::ffi::OsString;
 use std::fmt::{self, Display, Formatter};
-use std::fs;
+use std::fs::{self, File};
+use std::os::fd::AsRawFd;
 use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use nix::fcntl::{flock, FlockArg};
+use nix::sys::statfs::{statfs, FsType, NFS_SUPER_MAGIC};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::debug;
 
 use crate::data::CanonicalPath;
 
+/// `f_type` of an SMB mount, as reported by `statfs(2)`
+const SMB_SUPER_MAGIC: FsType = FsType(0x517b);
+/// `f_type` of a CIFS mount, as reported by `statfs(2)`
+const CIFS_SUPER_MAGIC: FsType = FsType(0xff53_4d42_u32 as i64);
+
+/// Name of the lock file used to guard registry mutations, ignored when iterating entries
+const LOCK_FILE_NAME: &str = ".lock";
+
+/// Suffix of the sidecar file holding an entry's [EntryMetadata]
+const META_FILE_SUFFIX: &str = ".meta";
+
+/// How registrations are persisted on disk
+///
+/// Symlinks are cheap and self-describing, but network filesystems like NFS
+/// and SMB/CIFS have weak cross-client cache coherence for symlink creation
+/// and resolution, which can make registrations appear to vanish or
+/// duplicate. On such filesystems we fall back to plain files instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Symlink,
+    RegularFile,
+}
+
+/// Detect whether `state_dir` lives on a network filesystem and pick the
+/// appropriate backend
+fn detect_backend(state_dir: &Path) -> Backend {
+    let fs_type = match statfs(state_dir) {
+        Ok(stat) => stat.filesystem_type(),
+        Err(e) => {
+            debug!(%e, "failed to stat registry state dir filesystem, defaulting to symlinks");
+            return Backend::Symlink;
+        },
+    };
+
+    if fs_type == NFS_SUPER_MAGIC || fs_type == SMB_SUPER_MAGIC || fs_type == CIFS_SUPER_MAGIC {
+        debug!("registry state dir is on a network filesystem, using regular-file backend");
+        Backend::RegularFile
+    } else {
+        Backend::Symlink
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RegistryError {
     #[error("failed to create registry state directory: {0}")]
@@ -22,6 +69,56 @@ pub enum RegistryError {
 
     #[error("failed to read registry state directory: {0}")]
     ReadingStateDir(#[source] std::io::Error),
+
+    #[error("failed to lock registry state directory: {0}")]
+    Locking(#[source] std::io::Error),
+
+    #[error("failed to write registry entry metadata: {0}")]
+    WritingMetadata(#[source] std::io::Error),
+
+    #[error("failed to normalize path: {0}")]
+    NormalizingPath(#[source] std::io::Error),
+}
+
+/// Timestamps persisted alongside a registry entry
+///
+/// Stored as a small JSON sidecar file next to the entry itself, written
+/// atomically so a crash mid-write never leaves a half-written record.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct EntryMetadata {
+    /// Seconds since the Unix epoch at which this path was first registered
+    registered_at: u64,
+    /// Seconds since the Unix epoch at which this path was last (re-)registered
+    last_activated: u64,
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// A handle on an flock'd `.lock` file in the registry's state dir
+///
+/// The lock is released when the guard is dropped.
+struct LockGuard {
+    file: File,
+}
+
+impl LockGuard {
+    fn acquire(state_dir: &Path, arg: FlockArg) -> Result<Self, RegistryError> {
+        let file = File::create(state_dir.join(".lock")).map_err(RegistryError::Locking)?;
+        flock(file.as_raw_fd(), arg).map_err(|errno| RegistryError::Locking(errno.into()))?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = flock(self.file.as_raw_fd(), FlockArg::Unlock);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, derive_more::From, derive_more::AsRef)]
@@ -39,6 +136,8 @@ impl Display for RegistryKey {
 pub struct RegistryEntry {
     key: RegistryKey,
     path: PathBuf,
+    registered_at: SystemTime,
+    last_activated: SystemTime,
 }
 
 impl RegistryEntry {
@@ -53,23 +152,47 @@ impl RegistryEntry {
     pub fn exists(&self) -> bool {
         self.path.exists()
     }
+
+    /// The time at which this path was first registered
+    pub fn registered_at(&self) -> SystemTime {
+        self.registered_at
+    }
+
+    /// The time at which this path was last (re-)registered
+    pub fn last_activated(&self) -> SystemTime {
+        self.last_activated
+    }
 }
 
 #[derive(Debug)]
 pub struct LinkRegistry {
-    /// A directory containing symlinks to registered .flox directories
+    /// A directory containing registrations of .flox directories
     ///
+    /// Depending on the filesystem `state_dir` lives on, registrations are
+    /// either symlinks or regular files, see [Backend].
     /// Symlinks may become stale if the .flox directory is moved or deleted.
     state_dir: PathBuf,
+    backend: Backend,
 }
 
 impl LinkRegistry {
     ///
     pub fn open(registry_state_dir: impl AsRef<Path>) -> Result<Self, RegistryError> {
-        fs::create_dir_all(registry_state_dir.as_ref()).map_err(RegistryError::CreateStateDir)?;
-        Ok(Self {
-            state_dir: registry_state_dir.as_ref().to_path_buf(),
-        })
+        let state_dir = registry_state_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&state_dir).map_err(RegistryError::CreateStateDir)?;
+        let backend = detect_backend(&state_dir);
+        Ok(Self { state_dir, backend })
+    }
+
+    /// Open a registry with an explicit backend, bypassing filesystem detection
+    #[cfg(test)]
+    fn open_with_backend(
+        registry_state_dir: impl AsRef<Path>,
+        backend: Backend,
+    ) -> Result<Self, RegistryError> {
+        let state_dir = registry_state_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&state_dir).map_err(RegistryError::CreateStateDir)?;
+        Ok(Self { state_dir, backend })
     }
 
     /// Register a .flox directory
@@ -78,25 +201,64 @@ impl LinkRegistry {
     /// The ID is a semi-unique identifier for the directory.
     /// More precisely, the current implementation uses the blake3 hash of the canonicalized path.
     pub fn register(&self, path: &CanonicalPath) -> Result<RegistryKey, RegistryError> {
+        let _lock = LockGuard::acquire(&self.state_dir, FlockArg::LockExclusive)?;
+
         let name = encode_path(path);
+        let key: RegistryKey = name.into();
+        let entry_path = self.state_dir.join(&key);
+
+        let already_registered = match self.backend {
+            Backend::Symlink => entry_path.symlink_metadata().is_ok(),
+            Backend::RegularFile => entry_path.exists(),
+        };
+
+        let now = unix_secs(SystemTime::now());
+        let meta = if already_registered {
+            let mut meta = self.read_metadata(&key);
+            meta.last_activated = now;
+            meta
+        } else {
+            EntryMetadata {
+                registered_at: now,
+                last_activated: now,
+            }
+        };
 
-        let link_path = self.state_dir.join(&name);
+        if already_registered {
+            // The pointer is already there from a prior registration; only its
+            // metadata changes, so there's nothing to roll back if this fails.
+            self.write_metadata(&key, &meta)?;
+            return Ok(key);
+        }
 
-        let Err(e) = std::os::unix::fs::symlink(path, link_path) else {
-            return Ok(name.into());
+        // Write the metadata before the pointer, so a reader can never
+        // observe a pointer whose metadata hasn't been written yet: the
+        // pointer is what makes an entry visible to `get`/`try_iter`.
+        self.write_metadata(&key, &meta)?;
+
+        let created = match self.backend {
+            Backend::Symlink => {
+                std::os::unix::fs::symlink(path, &entry_path).map_err(RegistryError::CreatingLink)
+            },
+            Backend::RegularFile => write_atomic(&entry_path, path.as_os_str().as_bytes())
+                .map_err(RegistryError::CreatingLink),
         };
 
-        match e.kind() {
-            std::io::ErrorKind::AlreadyExists => Ok(name.into()),
-            _ => Err(RegistryError::CreatingLink(e)),
+        if let Err(e) = created {
+            let _ = std::fs::remove_file(self.meta_path(&key));
+            return Err(e);
         }
+
+        Ok(key)
     }
 
     /// Remove a .flox directory from the registry
     ///
     /// If the directory is not registered, this is a no-op.
     pub fn unregister(&self, key: &RegistryKey) -> Result<Option<RegistryEntry>, RegistryError> {
-        let Some(entry) = self.get(key) else {
+        let _lock = LockGuard::acquire(&self.state_dir, FlockArg::LockExclusive)?;
+
+        let Some(entry) = self.get_unlocked(key) else {
             debug!(key = ?key, "entry not found, nothing to unregister");
             return Ok(None);
         };
@@ -104,25 +266,99 @@ impl LinkRegistry {
         let link_path = self.state_dir.join(key);
 
         std::fs::remove_file(link_path).map_err(RegistryError::RemovingLink)?;
+        let _ = std::fs::remove_file(self.meta_path(key));
 
         Ok(Some(entry))
     }
 
+    /// Remove the registration for `path`, even if `path` no longer exists
+    ///
+    /// Unlike [LinkRegistry::register], this does not require `path` to
+    /// resolve to an existing `.flox` directory: the registry key is
+    /// recomputed from `path` after lexically normalizing it (see
+    /// [normalize_path]), so a moved or deleted environment can still be
+    /// unregistered by its original location. Symlinks in `path` are not
+    /// resolved, so the key matches the one computed at registration time
+    /// only if `path` normalizes to that registration's canonical path.
+    pub fn unregister_path(&self, path: &Path) -> Result<Option<RegistryEntry>, RegistryError> {
+        let normalized = normalize_path(path).map_err(RegistryError::NormalizingPath)?;
+        let key: RegistryKey = encode_path_bytes(normalized.as_os_str().as_bytes()).into();
+        self.unregister(&key)
+    }
+
+    fn meta_path(&self, key: &RegistryKey) -> PathBuf {
+        self.state_dir
+            .join(format!("{key}{META_FILE_SUFFIX}"))
+    }
+
+    /// Read the metadata sidecar for `key`
+    ///
+    /// Entries registered before metadata tracking was introduced (or whose
+    /// sidecar was lost, e.g. by a rolled-back registration) have no sidecar
+    /// yet. For those, timestamps are derived from the entry's own mtime
+    /// rather than the current time, and the result is persisted so repeated
+    /// reads of the same un-migrated entry agree with each other.
+    fn read_metadata(&self, key: &RegistryKey) -> EntryMetadata {
+        if let Some(meta) = fs::read(self.meta_path(key))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        {
+            return meta;
+        }
+
+        let entry_path = self.state_dir.join(key);
+        let mtime = fs::symlink_metadata(&entry_path)
+            .and_then(|m| m.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+
+        let fallback = EntryMetadata {
+            registered_at: unix_secs(mtime),
+            last_activated: unix_secs(mtime),
+        };
+        let _ = self.write_metadata(key, &fallback);
+
+        fallback
+    }
+
+    fn write_metadata(&self, key: &RegistryKey, meta: &EntryMetadata) -> Result<(), RegistryError> {
+        let bytes = serde_json::to_vec(meta).expect("EntryMetadata always serializes");
+        write_atomic(&self.meta_path(key), &bytes).map_err(RegistryError::WritingMetadata)
+    }
+
     /// Iterate all entries in the registry
     ///
-    /// Returns an iterator over all entries in the registry,
-    /// that is *all symlinks in the registry directory*.
+    /// Returns an iterator over all entries in the registry directory, that
+    /// is *every entry pointer* (a symlink or a regular file, depending on
+    /// the registry's [Backend]), excluding the `.lock` file and `.meta`
+    /// sidecars.
     ///
     /// The iterator yields `RegistryEntry` instances for all entries,
     /// including those that are not valid links or links to .flox directories.
     pub fn try_iter(&self) -> Result<impl Iterator<Item = RegistryEntry>, RegistryError> {
+        let _lock = LockGuard::acquire(&self.state_dir, FlockArg::LockShared)?;
+        self.try_iter_unlocked()
+    }
+
+    fn try_iter_unlocked(&self) -> Result<impl Iterator<Item = RegistryEntry>, RegistryError> {
         let entries = std::fs::read_dir(&self.state_dir).map_err(RegistryError::ReadingStateDir)?;
+        let backend = self.backend;
 
-        let iter = entries.filter_map(|entry| {
+        let iter = entries.filter_map(move |entry| {
             let entry = entry.ok()?;
-            let path = entry.path().read_link().ok()?;
-            let key = entry.file_name().into();
-            Some(RegistryEntry { key, path })
+            let file_name = entry.file_name();
+            if file_name == LOCK_FILE_NAME || file_name.to_string_lossy().ends_with(META_FILE_SUFFIX)
+            {
+                return None;
+            }
+            let path = read_entry(&entry.path(), backend).ok()?;
+            let key: RegistryKey = file_name.into();
+            let meta = self.read_metadata(&key);
+            Some(RegistryEntry {
+                key,
+                path,
+                registered_at: from_unix_secs(meta.registered_at),
+                last_activated: from_unix_secs(meta.last_activated),
+            })
         });
 
         Ok(iter)
@@ -130,16 +366,56 @@ impl LinkRegistry {
 
     /// Get a .flox directory by its ID
     pub fn get(&self, key: &RegistryKey) -> Option<RegistryEntry> {
-        let Some(target) = self.state_dir.join(key).read_link().ok() else {
-            debug!(key = ?key, "link not found for requested id");
+        let _lock = LockGuard::acquire(&self.state_dir, FlockArg::LockShared).ok()?;
+        self.get_unlocked(key)
+    }
+
+    fn get_unlocked(&self, key: &RegistryKey) -> Option<RegistryEntry> {
+        let Some(target) = read_entry(&self.state_dir.join(key), self.backend).ok() else {
+            debug!(key = ?key, "entry not found for requested id");
             return None;
         };
 
+        let meta = self.read_metadata(key);
+
         Some(RegistryEntry {
             key: key.clone(),
             path: target,
+            registered_at: from_unix_secs(meta.registered_at),
+            last_activated: from_unix_secs(meta.last_activated),
         })
     }
+
+    /// Remove stale entries from the registry and return what was removed
+    ///
+    /// An entry is considered stale if its target's parent directory exists,
+    /// but the target itself (the `.flox` directory) does not.
+    /// Requiring the parent to exist avoids pruning entries whose target is
+    /// merely unreachable due to a transiently unmounted volume.
+    pub fn prune(&self) -> Result<Vec<RegistryEntry>, RegistryError> {
+        let _lock = LockGuard::acquire(&self.state_dir, FlockArg::LockExclusive)?;
+
+        let mut pruned = Vec::new();
+
+        for entry in self.try_iter_unlocked()? {
+            if entry.exists() {
+                continue;
+            }
+
+            let parent_exists = entry.path().parent().is_some_and(Path::exists);
+            if !parent_exists {
+                debug!(path = ?entry.path(), "parent of target is unreachable, not pruning");
+                continue;
+            }
+
+            let link_path = self.state_dir.join(entry.key());
+            std::fs::remove_file(link_path).map_err(RegistryError::RemovingLink)?;
+            let _ = std::fs::remove_file(self.meta_path(entry.key()));
+            pruned.push(entry);
+        }
+
+        Ok(pruned)
+    }
 }
 
 trait Register {
@@ -148,7 +424,62 @@ trait Register {
 
 /// Returns a unique identifier for the location of the project.
 fn encode_path(path: &CanonicalPath) -> String {
-    blake3::hash(path.as_os_str().as_bytes()).to_string()
+    encode_path_bytes(path.as_os_str().as_bytes())
+}
+
+fn encode_path_bytes(path: &[u8]) -> String {
+    blake3::hash(path).to_string()
+}
+
+/// Lexically resolve `.`/`..` components and absolutize `path` against the
+/// current directory, without touching the filesystem
+///
+/// Unlike [std::fs::canonicalize] (which [CanonicalPath] wraps), this
+/// succeeds even if `path`, or any of its ancestors, no longer exists.
+fn normalize_path(path: &Path) -> std::io::Result<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            },
+            std::path::Component::CurDir => {},
+            other => normalized.push(other),
+        }
+    }
+
+    Ok(normalized)
+}
+
+/// Read the target path of a registry entry, dispatching on the backend
+/// that was used to write it
+fn read_entry(entry_path: &Path, backend: Backend) -> std::io::Result<PathBuf> {
+    match backend {
+        Backend::Symlink => entry_path.read_link(),
+        Backend::RegularFile => {
+            let bytes = fs::read(entry_path)?;
+            Ok(PathBuf::from(std::ffi::OsStr::from_bytes(&bytes)))
+        },
+    }
+}
+
+/// Write `contents` to `path`, making the write appear atomic to readers
+///
+/// The contents are written to a temporary file in the same directory as
+/// `path` and then moved into place with a single `rename(2)`, so readers
+/// never observe a partially written file.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    std::io::Write::write_all(&mut tmp, contents)?;
+    tmp.persist(path).map_err(|e| e.error)?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -282,4 +613,213 @@ mod tests {
         let entry2 = entries.iter().find(|e| e.key() == &key2).unwrap();
         assert_eq!(entry2.path(), &*target_dir2);
     }
+
+    /// Test that [LinkRegistry::prune] removes entries whose target is gone,
+    /// but keeps entries whose target's parent is also unreachable.
+    #[test]
+    fn test_prune() {
+        let (registry, tempdir) = create_registry();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+        let key = registry.register(&target_dir).unwrap();
+
+        let unmounted_target = tempdir.path().join("unmounted").join("test");
+        let unmounted_key: RegistryKey = "unmounted".into();
+        std::os::unix::fs::symlink(&unmounted_target, registry.state_dir.join(&unmounted_key))
+            .unwrap();
+
+        fs::remove_dir_all(&target_dir).unwrap();
+
+        let pruned = registry.prune().unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].key(), &key);
+
+        assert!(registry.get(&key).is_none());
+        assert!(registry.get(&unmounted_key).is_some());
+    }
+
+    /// Test that concurrent registration of distinct paths from multiple
+    /// threads does not lose or corrupt any links.
+    #[test]
+    fn test_concurrent_register_distinct_paths() {
+        let (registry, tempdir) = create_registry();
+        let registry = std::sync::Arc::new(registry);
+
+        let target_dirs: Vec<_> = (0..8)
+            .map(|i| create_target_dir(&tempdir, &format!("test{i}")))
+            .collect();
+
+        let handles: Vec<_> = target_dirs
+            .iter()
+            .cloned()
+            .map(|target_dir| {
+                let registry = registry.clone();
+                thread::spawn(move || registry.register(&target_dir).unwrap())
+            })
+            .collect();
+
+        let keys: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        let entries: Vec<_> = registry.try_iter().unwrap().collect();
+        assert_eq!(entries.len(), target_dirs.len());
+
+        for (key, target_dir) in keys.iter().zip(target_dirs.iter()) {
+            let entry = registry.get(key).unwrap();
+            assert_eq!(entry.path(), &**target_dir);
+        }
+    }
+
+    /// Test that concurrently registering and unregistering the same path
+    /// from multiple threads leaves the registry in a consistent state.
+    #[test]
+    fn test_concurrent_register_unregister_same_path() {
+        let (registry, tempdir) = create_registry();
+        let registry = std::sync::Arc::new(registry);
+
+        let target_dir = create_target_dir(&tempdir, "test");
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let registry = registry.clone();
+                let target_dir = target_dir.clone();
+                thread::spawn(move || {
+                    let key = registry.register(&target_dir).unwrap();
+                    registry.unregister(&key).unwrap();
+                    registry.register(&target_dir).unwrap()
+                })
+            })
+            .collect();
+
+        let keys: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // All threads registered the same path, so they must all agree on the key,
+        // and the registry must end up with exactly one entry for it.
+        assert!(keys.iter().all(|key| key == &keys[0]));
+        assert!(registry.get(&keys[0]).is_some());
+
+        let entries: Vec<_> = registry.try_iter().unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    /// Test that the symlink backend can be forced explicitly and behaves
+    /// like the default
+    #[test]
+    fn test_symlink_backend() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let registry =
+            LinkRegistry::open_with_backend(tempdir.path().join("registry"), Backend::Symlink)
+                .unwrap();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+        let key = registry.register(&target_dir).unwrap();
+
+        let link_path = registry.state_dir.join(&key);
+        assert!(link_path.symlink_metadata().unwrap().is_symlink());
+
+        let entry = registry.get(&key).unwrap();
+        assert_eq!(entry.path(), &*target_dir);
+    }
+
+    /// Test that the regular-file backend stores the target path in a plain
+    /// file instead of a symlink
+    #[test]
+    fn test_regular_file_backend() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let registry = LinkRegistry::open_with_backend(
+            tempdir.path().join("registry"),
+            Backend::RegularFile,
+        )
+        .unwrap();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+        let key = registry.register(&target_dir).unwrap();
+
+        let entry_path = registry.state_dir.join(&key);
+        assert!(!entry_path.symlink_metadata().unwrap().is_symlink());
+        assert_eq!(fs::read(&entry_path).unwrap(), target_dir.as_os_str().as_bytes());
+
+        let entry = registry.get(&key).unwrap();
+        assert_eq!(entry.path(), &*target_dir);
+
+        let removed = registry.unregister(&key).unwrap();
+        assert_eq!(removed.unwrap().path(), &*target_dir);
+        assert!(registry.get(&key).is_none());
+    }
+
+    /// Test that re-registering an existing path keeps `registered_at` but
+    /// bumps `last_activated`.
+    #[test]
+    fn test_register_twice_bumps_last_activated() {
+        let (registry, tempdir) = create_registry();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+
+        let key = registry.register(&target_dir).unwrap();
+        let first = registry.get(&key).unwrap();
+
+        thread::sleep(Duration::from_secs(1));
+
+        let key2 = registry.register(&target_dir).unwrap();
+        let second = registry.get(&key2).unwrap();
+
+        assert_eq!(key, key2);
+        assert_eq!(first.registered_at(), second.registered_at());
+        assert!(second.last_activated() > first.last_activated());
+    }
+
+    /// Test that [normalize_path] resolves `.`/`..` components without
+    /// requiring the path to exist
+    #[test]
+    fn test_normalize_path() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let nonexistent = tempdir.path().join("a/b/../c");
+
+        let normalized = normalize_path(&nonexistent).unwrap();
+
+        assert_eq!(normalized, tempdir.path().join("a/c"));
+    }
+
+    /// Test that the fallback metadata synthesized for an entry with no
+    /// `.meta` sidecar is derived from the entry's mtime, and is persisted so
+    /// repeated reads agree with each other instead of drifting to whatever
+    /// time they happened to run at.
+    #[test]
+    fn test_missing_metadata_fallback_is_stable() {
+        let (registry, tempdir) = create_registry();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+        let key: RegistryKey = "legacy".into();
+        std::os::unix::fs::symlink(&*target_dir, registry.state_dir.join(&key)).unwrap();
+
+        assert!(!registry.meta_path(&key).exists());
+
+        let first = registry.get(&key).unwrap();
+        assert!(registry.meta_path(&key).exists());
+
+        thread::sleep(Duration::from_secs(1));
+
+        let second = registry.get(&key).unwrap();
+        assert_eq!(first.registered_at(), second.registered_at());
+        assert_eq!(first.last_activated(), second.last_activated());
+    }
+
+    /// Test that a moved or deleted environment can be unregistered by its
+    /// original location, even though it no longer canonicalizes.
+    #[test]
+    fn test_unregister_path_after_deletion() {
+        let (registry, tempdir) = create_registry();
+
+        let target_dir = create_target_dir(&tempdir, "test");
+        let original_path = target_dir.to_path_buf();
+
+        let key = registry.register(&target_dir).unwrap();
+        assert!(registry.get(&key).is_some());
+
+        fs::remove_dir_all(&target_dir).unwrap();
+        assert!(CanonicalPath::new(&original_path).is_err());
+
+        let removed = registry.unregister_path(&original_path).unwrap();
+        assert_eq!(removed.unwrap().key(), &key);
+        assert!(registry.get(&key).is_none());
+    }
 }
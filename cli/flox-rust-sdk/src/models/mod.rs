@@ -1,5 +1,6 @@
 //# An attempt at defining a domain model for flox
 pub mod container_builder;
+pub mod discover;
 pub mod environment;
 pub mod environment_ref;
 pub mod floxmetav2;
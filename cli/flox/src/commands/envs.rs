@@ -1,9 +1,14 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
 use anyhow::Result;
 use bpaf::Bpaf;
 use flox_rust_sdk::data::CanonicalPath;
 use flox_rust_sdk::flox::Flox;
+use flox_rust_sdk::models::discover::discover_dot_flox_dirs;
 use flox_rust_sdk::models::environment::DotFlox;
 use flox_rust_sdk::models::link_registry::{LinkRegistry, RegistryError, RegistryKey};
+use serde::Serialize;
 use serde_json::json;
 use tracing::instrument;
 
@@ -17,6 +22,17 @@ pub struct Envs {
     active: bool,
     #[bpaf(long)]
     json: bool,
+    /// Remove registry entries whose .flox directory no longer exists but
+    /// whose parent directory still does
+    #[bpaf(long)]
+    prune: bool,
+    /// Recursively find and register .flox directories under DIR
+    #[bpaf(long, argument("DIR"))]
+    discover: Option<PathBuf>,
+    /// Unregister the environment originally registered at PATH, even if it
+    /// has since been moved or deleted
+    #[bpaf(long, argument("PATH"))]
+    forget: Option<PathBuf>,
 }
 
 impl Envs {
@@ -24,21 +40,81 @@ impl Envs {
     pub fn handle(self, flox: Flox) -> Result<()> {
         subcommand_metric!("envs");
 
-        let active = activated_environments();
         let available = RegisteredEnvironments::new(&flox)?;
 
-        println!(
-            "{}",
-            json!({
-                "active": active,
-                "available": available.try_iter()?.collect::<Vec<_>>()
-            })
-        );
+        if self.prune {
+            let pruned = available.prune()?;
+            println!(
+                "{}",
+                json!({
+                    "pruned": pruned
+                })
+            );
+            return Ok(());
+        }
+
+        if let Some(path) = &self.forget {
+            let forgotten = available.unregister_path(path)?;
+            println!(
+                "{}",
+                json!({
+                    "forgotten": forgotten
+                })
+            );
+            return Ok(());
+        }
+
+        if let Some(dir) = &self.discover {
+            let report = available.discover(dir)?;
+            println!(
+                "{}",
+                json!({
+                    "newly_registered": report.newly_registered,
+                    "already_registered": report.already_registered,
+                })
+            );
+            return Ok(());
+        }
+
+        let mut available = available.try_iter()?.collect::<Vec<_>>();
+        available.sort_by(|a, b| b.last_activated.cmp(&a.last_activated));
+
+        if self.json {
+            let active = activated_environments();
+            println!(
+                "{}",
+                json!({
+                    "active": active,
+                    "available": available
+                })
+            );
+        } else {
+            for env in &available {
+                if let Some(path) = env.env.path() {
+                    println!("{}", path.display());
+                }
+            }
+        }
 
         Ok(())
     }
 }
 
+/// A registered environment together with the metadata used to order it in `flox envs`
+#[derive(Serialize)]
+struct AvailableEnvironment {
+    #[serde(flatten)]
+    env: UninitializedEnvironment,
+    last_activated: SystemTime,
+}
+
+/// Outcome of [RegisteredEnvironments::discover]
+#[derive(Debug, Default)]
+pub struct DiscoverReport {
+    pub newly_registered: usize,
+    pub already_registered: usize,
+}
+
 pub struct RegisteredEnvironments {
     registry: LinkRegistry,
 }
@@ -66,10 +142,68 @@ impl RegisteredEnvironments {
         Ok(())
     }
 
-    fn try_iter(&self) -> Result<impl Iterator<Item = UninitializedEnvironment>> {
+    /// Unregister an environment by the path it was originally registered
+    /// under, even if that path has since been moved or deleted
+    ///
+    /// Returns the path that was removed, or `None` if nothing was
+    /// registered under it.
+    pub fn unregister_path(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let removed = self
+            .registry
+            .unregister_path(path)?
+            .map(|entry| entry.path().to_path_buf());
+        Ok(removed)
+    }
+
+    /// Remove registry entries whose .flox directory has been moved or deleted
+    ///
+    /// Entries whose .flox directory's parent is missing as well are kept,
+    /// see [LinkRegistry::prune].
+    ///
+    /// Returns the paths that were pruned.
+    pub fn prune(&self) -> Result<Vec<PathBuf>> {
+        let pruned = self
+            .registry
+            .prune()?
+            .into_iter()
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
+        Ok(pruned)
+    }
+
+    /// Recursively find `.flox` directories under `dir` and register each one
+    pub fn discover(&self, dir: &Path) -> Result<DiscoverReport> {
+        let already_registered: std::collections::HashSet<PathBuf> = self
+            .registry
+            .try_iter()?
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
+
+        let mut report = DiscoverReport::default();
+        for dot_flox_dir in discover_dot_flox_dirs(dir)? {
+            let Ok(canonical) = CanonicalPath::new(&dot_flox_dir) else {
+                continue;
+            };
+
+            self.registry.register(&canonical)?;
+
+            if already_registered.contains(&*canonical) {
+                report.already_registered += 1;
+            } else {
+                report.newly_registered += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn try_iter(&self) -> Result<impl Iterator<Item = AvailableEnvironment>> {
         let iter = self.registry.try_iter()?.filter_map(|entry| {
             let dot_flox = DotFlox::open(entry.path()).ok()?;
-            Some(UninitializedEnvironment::DotFlox(dot_flox))
+            Some(AvailableEnvironment {
+                env: UninitializedEnvironment::DotFlox(dot_flox),
+                last_activated: entry.last_activated(),
+            })
         });
         Ok(iter)
     }